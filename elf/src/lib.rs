@@ -18,7 +18,7 @@
 //! [elfspec]: http://www.skyfree.org/linux/references/ELF_Format.pdf
 #![feature(core_intrinsics)]
 #![feature(try_from)]
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
 #[macro_use] extern crate bitflags;
 #[macro_use] extern crate macro_attr;
@@ -49,6 +49,8 @@ macro_rules! impl_getters {
 pub mod section;
 pub mod file;
 pub mod program;
+pub mod symbol;
+pub mod load;
 
 /// An ELF section header.
 pub type Section<'a> = section::Header<'a>;
@@ -61,9 +63,14 @@ pub type ElfResult<T> = Result<T, &'static str>;
 pub trait ElfWord: Sized + Copy + Clone
                          + ops::Add<Self> + ops::Sub<Self>
                          + ops::Mul<Self> + ops::Div<Self>
-                         + ops::Shl<Self> + ops::Shr<Self> { }
-impl ElfWord for u64 { }
-impl ElfWord for u32 { }
+                         + ops::Shl<Self> + ops::Shr<Self> {
+    /// The symbol table entry type matching this word size, so that
+    /// `Image::symbols()` decodes `.symtab` using the same width as the
+    /// rest of the image, rather than the host's own pointer width.
+    type Symbol: symbol::Symbol;
+}
+impl ElfWord for u64 { type Symbol = symbol::Symbol64; }
+impl ElfWord for u32 { type Symbol = symbol::Symbol32; }
 
 #[cfg(target_pointer_width = "32")]
 type DefaultWord = u32;
@@ -118,6 +125,96 @@ where Word: ElfWord + 'a
         section::StrTable::from(&self.binary[self.header.sh_str_idx()..])
     }
 
+    /// Returns an iterator over the symbols in this image's `.symtab`
+    /// section, paired with their names resolved through the linked
+    /// `.strtab`, or `None` if the image has no symbol table.
+    pub fn symbols(&'a self) -> Option<symbol::Symbols<'a, Word::Symbol>> {
+        let symtab = self.sections.iter()
+            .find(|s| s.sh_type() == symbol::SHT_SYMTAB)?;
+        let strtab = self.sections.get(symtab.sh_link() as usize)?;
+
+        let entries = unsafe { extract_from_slice::<Word::Symbol>(
+            &self.binary[symtab.sh_offset() as usize..]
+          , 0
+          , (symtab.sh_size() / symtab.sh_entsize()) as usize
+        ) }.ok()?;
+        let strings = &self.binary[ strtab.sh_offset() as usize
+                                   ..(strtab.sh_offset() + strtab.sh_size()) as usize ];
+
+        Some(symbol::Symbols { entries: entries.iter(), strings: strings })
+    }
+
+    /// Resolve a code address to the name of, and offset into, the
+    /// function containing it, if one can be found in this image's
+    /// symbol table.
+    pub fn resolve(&'a self, addr: usize) -> Option<(&'a str, usize)> {
+        symbol::resolve(self.symbols()?, addr)
+    }
+
+    /// Load this image's `PT_LOAD` segments into the address space
+    /// administered by `mapper`, copying each segment's file contents and
+    /// zero-filling its BSS tail, and return the entry point recorded in
+    /// the file header.
+    ///
+    /// Segments aren't generally page-aligned to each other, so the tail
+    /// of one segment (e.g. a read-only `.text`) and the head of the next
+    /// (e.g. a writable `.data`) can legitimately share the same page.
+    /// `mapper.map_page` is documented to hand back a freshly allocated
+    /// frame on every call, so calling it twice for that shared page
+    /// would silently discard whatever the first segment already wrote
+    /// into it. To avoid that, this remembers the last page-aligned
+    /// address it mapped, across segments, and keeps writing into the
+    /// same returned buffer for as long as consecutive writes land on
+    /// it -- only calling `map_page` again once the address moves to a
+    /// new page.
+    pub fn load_into<M>(&'a self, mapper: &mut M) -> usize
+    where M: load::Mapper {
+        use memory::PAGE_SIZE;
+
+        let mut last_page: Option<(usize, &mut [u8])> = None;
+
+        for ph in self.program_headers.iter()
+            .filter(|ph| ph.p_type() == load::PT_LOAD) {
+
+            let vaddr = ph.p_vaddr() as usize;
+            let file_size = ph.p_filesz() as usize;
+            let mem_size = ph.p_memsz() as usize;
+            let flags = load::entry_flags(ph.p_flags() as u32);
+            let data = &self.binary[ph.p_offset() as usize..][..file_size];
+
+            let mut written = 0;
+            while written < mem_size {
+                let page_vaddr = vaddr + written;
+                let page_off = page_vaddr % PAGE_SIZE;
+                let page_base = page_vaddr - page_off;
+
+                if last_page.as_ref().map(|&(base, _)| base) != Some(page_base) {
+                    last_page = Some((page_base, mapper.map_page(page_base, flags)));
+                }
+                let page = &mut last_page.as_mut().unwrap().1;
+
+                let in_page = ::core::cmp::min(PAGE_SIZE - page_off, mem_size - written);
+
+                if written < file_size {
+                    let from_file = ::core::cmp::min(in_page, file_size - written);
+                    page[page_off..page_off + from_file]
+                        .copy_from_slice(&data[written..written + from_file]);
+                    for byte in &mut page[page_off + from_file..page_off + in_page] {
+                        *byte = 0;
+                    }
+                } else {
+                    for byte in &mut page[page_off..page_off + in_page] {
+                        *byte = 0;
+                    }
+                }
+
+                written += in_page;
+            }
+        }
+
+        self.header.entry_point() as usize
+    }
+
 }
 
 impl<'a, Word, PH, H> TryFrom<&'a [u8]> for Image<'a, Word, PH, H>
@@ -0,0 +1,88 @@
+//
+//  SOS: the Stupid Operating System
+//  by Eliza Weisman (hi@hawkweisman.me)
+//
+//  Copyright (c) 2015-2017 Eliza Weisman
+//  Released under the terms of the MIT license. See `LICENSE` in the root
+//  directory of this repository for more information.
+//
+//! Loading `PT_LOAD` program-header segments into a target address space.
+//!
+//! This module only decides _what_ needs to be mapped; it has no opinion
+//! on _how_ the target architecture represents an address space. The
+//! [`Mapper`](trait.Mapper.html) trait is the seam: the kernel supplies an
+//! implementation backed by `memory` and `arch::cpu::control_regs::cr3`,
+//! and `Image::load_into` drives it.
+
+/// Program-header type value for a loadable segment (`PT_LOAD`).
+pub const PT_LOAD: u32 = 1;
+
+/// `p_flags` bit indicating the segment is executable.
+const PF_X: u32 = 1 << 0;
+/// `p_flags` bit indicating the segment is writable.
+const PF_W: u32 = 1 << 1;
+
+bitflags! {
+    /// Flags describing how a loaded page should be mapped, decoded from
+    /// an ELF program header's `p_flags`.
+    pub flags EntryFlags: u8 {
+        /// The page is present in the address space. Always set for a
+        /// loaded `PT_LOAD` segment.
+        const PRESENT = 1 << 0
+      , /// The page is writable.
+        const WRITABLE = 1 << 1
+      , /// The page may be executed. If unset, the mapper should mark
+        /// the page no-execute (NX) where the architecture supports it.
+        const EXECUTABLE = 1 << 2
+    }
+}
+
+/// Decode a program header's `p_flags` into the `EntryFlags` a `Mapper`
+/// should apply to the pages backing that segment.
+pub fn entry_flags(p_flags: u32) -> EntryFlags {
+    let mut flags = PRESENT;
+    if p_flags & PF_W != 0 { flags |= WRITABLE; }
+    if p_flags & PF_X != 0 { flags |= EXECUTABLE; }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_flags_always_present() {
+        assert_eq!(entry_flags(0), PRESENT);
+    }
+
+    #[test]
+    fn entry_flags_writable() {
+        assert_eq!(entry_flags(PF_W), PRESENT | WRITABLE);
+    }
+
+    #[test]
+    fn entry_flags_executable() {
+        assert_eq!(entry_flags(PF_X), PRESENT | EXECUTABLE);
+    }
+
+    #[test]
+    fn entry_flags_writable_and_executable() {
+        assert_eq!(entry_flags(PF_W | PF_X), PRESENT | WRITABLE | EXECUTABLE);
+    }
+}
+
+/// An abstraction over a page-table mapper for a single address space.
+///
+/// `Image::load_into` is generic over `Mapper` so that it does not need
+/// to know how the target architecture represents or switches address
+/// spaces -- the caller supplies an implementation wrapping its own
+/// `memory` module and the address space it wants loaded into (e.g. one
+/// selected by writing `arch::cpu::control_regs::cr3`).
+pub trait Mapper {
+    /// Map a single page at `vaddr` (which must be page-aligned), backed
+    /// by a freshly allocated frame, with the given `flags`.
+    ///
+    /// Returns a mutable view of the newly-mapped page so its contents
+    /// can be initialized with segment data and/or zero-filled.
+    fn map_page<'a>(&'a mut self, vaddr: usize, flags: EntryFlags) -> &'a mut [u8];
+}
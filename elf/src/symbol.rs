@@ -0,0 +1,148 @@
+//
+//  SOS: the Stupid Operating System
+//  by Eliza Weisman (hi@hawkweisman.me)
+//
+//  Copyright (c) 2015-2017 Eliza Weisman
+//  Released under the terms of the MIT license. See `LICENSE` in the root
+//  directory of this repository for more information.
+//
+//! ELF symbol tables (`.symtab`) and their associated string tables
+//! (`.strtab`).
+use core::str;
+
+/// Section type value identifying a symbol table section (`SHT_SYMTAB`).
+pub const SHT_SYMTAB: u32 = 2;
+
+/// The symbol type is a function (`STT_FUNC`), as opposed to e.g. an
+/// object, a section, or a file.
+pub const STT_FUNC: u8 = 2;
+
+/// A 32-bit ELF symbol table entry (`Elf32_Sym`).
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct Symbol32 { st_name: u32
+                     , st_value: u32
+                     , st_size: u32
+                     , st_info: u8
+                     , st_other: u8
+                     , st_shndx: u16
+                     }
+
+/// A 64-bit ELF symbol table entry (`Elf64_Sym`).
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct Symbol64 { st_name: u32
+                     , st_info: u8
+                     , st_other: u8
+                     , st_shndx: u16
+                     , st_value: u64
+                     , st_size: u64
+                     }
+
+/// Behaviour common to 32- and 64-bit symbol table entries.
+pub trait Symbol {
+    /// The byte offset of this symbol's name into the associated
+    /// string table.
+    fn name_idx(&self) -> usize;
+    /// The value of this symbol (for a function, its entry address).
+    fn value(&self) -> usize;
+    /// The size of this symbol, in bytes.
+    fn size(&self) -> usize;
+    /// The low nibble of `st_info`, giving the symbol's type (e.g.
+    /// [`STT_FUNC`](constant.STT_FUNC.html)).
+    fn sym_type(&self) -> u8;
+
+    /// Returns `true` if this symbol describes a function.
+    #[inline]
+    fn is_func(&self) -> bool { self.sym_type() == STT_FUNC }
+}
+
+impl Symbol for Symbol32 {
+    #[inline] fn name_idx(&self) -> usize { self.st_name as usize }
+    #[inline] fn value(&self) -> usize { self.st_value as usize }
+    #[inline] fn size(&self) -> usize { self.st_size as usize }
+    #[inline] fn sym_type(&self) -> u8 { self.st_info & 0xf }
+}
+
+impl Symbol for Symbol64 {
+    #[inline] fn name_idx(&self) -> usize { self.st_name as usize }
+    #[inline] fn value(&self) -> usize { self.st_value as usize }
+    #[inline] fn size(&self) -> usize { self.st_size as usize }
+    #[inline] fn sym_type(&self) -> u8 { self.st_info & 0xf }
+}
+
+/// Read a NUL-terminated string out of an ELF string table at `offset`.
+fn read_cstr(strings: &[u8], offset: usize) -> &str {
+    let bytes = &strings[offset..];
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    str::from_utf8(&bytes[..len]).unwrap_or("<invalid utf8>")
+}
+
+/// An iterator over the entries of an ELF symbol table, pairing each
+/// [`Symbol`](trait.Symbol.html) with its name resolved through the
+/// image's string table.
+#[derive(Clone)]
+pub struct Symbols<'a, Sym: 'a> { pub(crate) entries: ::core::slice::Iter<'a, Sym>
+                                , pub(crate) strings: &'a [u8]
+                                }
+
+impl<'a, Sym> Iterator for Symbols<'a, Sym>
+where Sym: Symbol + 'a {
+    type Item = (&'a str, &'a Sym);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+            .map(|sym| (read_cstr(self.strings, sym.name_idx()), sym))
+    }
+}
+
+/// Resolve `addr` to the name of, and offset into, the function symbol
+/// containing it, given an iterator of `(name, symbol)` pairs.
+///
+/// This is the pure lookup [`Image::resolve`](../struct.Image.html#method.resolve)
+/// drives, factored out here so it can be exercised without needing a
+/// full parsed ELF image. `addr` is contained by a symbol when it falls
+/// in the half-open range `[sym.value(), sym.value() + sym.size())`;
+/// symbols that aren't `STT_FUNC` are never matched.
+pub fn resolve<'a, Sym>(symbols: Symbols<'a, Sym>, addr: usize) -> Option<(&'a str, usize)>
+where Sym: Symbol + 'a {
+    symbols
+        .filter(|&(_, sym)| sym.is_func())
+        .find(|&(_, sym)| addr >= sym.value() && addr < sym.value() + sym.size())
+        .map(|(name, sym)| (name, addr - sym.value()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `STT_OBJECT` symbol type (a data object, not a function).
+    const STT_OBJECT: u8 = 1;
+
+    fn sym(value: u64, size: u64, sym_type: u8) -> Symbol64 {
+        Symbol64 { st_name: 0, st_info: sym_type, st_other: 0, st_shndx: 0
+                 , st_value: value, st_size: size
+                 }
+    }
+
+    #[test]
+    fn resolve_matches_exact_start() {
+        let syms = [sym(0x1000, 0x10, STT_FUNC)];
+        let symbols = Symbols { entries: syms.iter(), strings: b"\0" };
+        assert_eq!(resolve(symbols, 0x1000), Some(("", 0)));
+    }
+
+    #[test]
+    fn resolve_excludes_end_exclusive() {
+        let syms = [sym(0x1000, 0x10, STT_FUNC)];
+        let symbols = Symbols { entries: syms.iter(), strings: b"\0" };
+        assert_eq!(resolve(symbols, 0x1010), None);
+    }
+
+    #[test]
+    fn resolve_excludes_non_func_symbols() {
+        let syms = [sym(0x1000, 0x10, STT_OBJECT)];
+        let symbols = Symbols { entries: syms.iter(), strings: b"\0" };
+        assert_eq!(resolve(symbols, 0x1000), None);
+    }
+}
@@ -0,0 +1,39 @@
+//
+//  SOS: the Stupid Operating System
+//  by Hawk Weisman (hi@hawkweisman.me)
+//
+//  Copyright (c) 2015 Hawk Weisman
+//  Released under the terms of the MIT license. See `LICENSE` in the root
+//  directory of this repository for more information.
+//
+//! Flags for the `%cr0` control register.
+bitflags! {
+    /// Flags controlling basic CPU operating modes, held in `%cr0`.
+    pub flags Flags: usize {
+        /// Protection Enable -- enables protected mode.
+        const PE = 1 << 0
+      , /// Monitor Co-processor -- controls `wait`/`fwait` trapping.
+        const MP = 1 << 1
+      , /// Emulation -- traps all floating-point instructions.
+        const EM = 1 << 2
+      , /// Task Switched -- set on every hardware task switch.
+        const TS = 1 << 3
+      , /// Extension Type -- hardcoded to 1 on 486+; present for
+        /// compatibility with the 387 co-processor.
+        const ET = 1 << 4
+      , /// Numeric Error -- enables native `x87` error reporting.
+        const NE = 1 << 5
+      , /// Write Protect -- when set, the CPU honors the read-only bit
+        /// of page-table entries even while in supervisor mode.
+        const WP = 1 << 16
+      , /// Alignment Mask -- enables alignment-check exceptions.
+        const AM = 1 << 18
+      , /// Not Write-through -- disables write-through caching and
+        /// cache invalidation.
+        const NW = 1 << 29
+      , /// Cache Disable -- disables the CPU's internal caches.
+        const CD = 1 << 30
+      , /// Paging -- enables paging.
+        const PG = 1 << 31
+    }
+}
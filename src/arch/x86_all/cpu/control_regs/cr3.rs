@@ -0,0 +1,49 @@
+//
+//  SOS: the Stupid Operating System
+//  by Hawk Weisman (hi@hawkweisman.me)
+//
+//  Copyright (c) 2015 Hawk Weisman
+//  Released under the terms of the MIT license. See `LICENSE` in the root
+//  directory of this repository for more information.
+//
+//! Decoding of the `%cr3` control register (the page-table root pointer).
+use core::fmt;
+
+use memory::PAddr;
+
+bitflags! {
+    /// `%cr3`'s page-level cache-control bits.
+    ///
+    /// These are only meaningful when `CR4.PCIDE` is unset; with PCID
+    /// enabled, the low 12 bits of `%cr3` instead hold the PCID.
+    pub flags Flags: usize {
+        /// Page-level Write-Through.
+        const PWT = 1 << 3
+      , /// Page-level Cache Disable.
+        const PCD = 1 << 4
+    }
+}
+
+/// A decoded `%cr3` value: the physical base address of the top-level
+/// page table (PML4, on `x86_64`, or the page directory, on `x86`), plus
+/// its cache-control bits.
+#[derive(Copy, Clone, Debug)]
+pub struct Cr3 { /// The physical base address of the top-level page table.
+                 pub base: PAddr
+               , /// The `PCD`/`PWT` cache-control bits.
+                 pub flags: Flags
+               }
+
+impl From<usize> for Cr3 {
+    fn from(value: usize) -> Self {
+        Cr3 { base: PAddr::from(value & !0xFFF)
+            , flags: Flags::from_bits_truncate(value & 0xFFF)
+            }
+    }
+}
+
+impl fmt::Display for Cr3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x} ({:?})", self.base, self.flags)
+    }
+}
@@ -0,0 +1,56 @@
+//
+//  SOS: the Stupid Operating System
+//  by Hawk Weisman (hi@hawkweisman.me)
+//
+//  Copyright (c) 2015 Hawk Weisman
+//  Released under the terms of the MIT license. See `LICENSE` in the root
+//  directory of this repository for more information.
+//
+//! Flags for the `%cr4` control register.
+bitflags! {
+    /// Flags controlling protected-mode operation, held in `%cr4`.
+    pub flags Flags: usize {
+        /// Virtual-8086 Mode Extensions.
+        const VME = 1 << 0
+      , /// Protected-Mode Virtual Interrupts.
+        const PVI = 1 << 1
+      , /// Time Stamp Disable.
+        const TSD = 1 << 2
+      , /// Debugging Extensions.
+        const DE = 1 << 3
+      , /// Page Size Extension.
+        const PSE = 1 << 4
+      , /// Physical Address Extension.
+        const PAE = 1 << 5
+      , /// Machine Check Exception.
+        const MCE = 1 << 6
+      , /// Page Global Enable.
+        const PGE = 1 << 7
+      , /// Performance-Monitoring Counter Enable.
+        const PCE = 1 << 8
+      , /// OS support for `fxsave`/`fxrstor` (`OSFXSR`).
+        const OSFXSR = 1 << 9
+      , /// OS support for unmasked SSE floating-point exceptions
+        /// (`OSXMMEXCPT`).
+        const OSXMMEXCPT = 1 << 10
+      , /// User-Mode Instruction Prevention.
+        const UMIP = 1 << 11
+      , /// Virtual Machine Extensions Enable.
+        const VMXE = 1 << 13
+      , /// Safer Mode Extensions Enable.
+        const SMXE = 1 << 14
+      , /// `fsgsbase` Enable.
+        const FSGSBASE = 1 << 16
+      , /// PCID Enable.
+        const PCIDE = 1 << 17
+      , /// OS support for `xsave`/`xrstor`, and for managing extended
+        /// processor state via `xsetbv`/`xgetbv` (`OSXSAVE`).
+        const OSXSAVE = 1 << 18
+      , /// Supervisor-Mode Execution Prevention.
+        const SMEP = 1 << 20
+      , /// Supervisor-Mode Access Prevention.
+        const SMAP = 1 << 21
+      , /// Protection-Key Enable.
+        const PKE = 1 << 22
+    }
+}
@@ -9,32 +9,41 @@
 //! `x86` and `x86_64` control registers
 use core::fmt;
 
+/// `%cr0` contains flags that control basic CPU operating modes.
+pub mod cr0;
+/// `%cr3` contains the page table root pointer and its cache-control bits.
+pub mod cr3;
 /// `%cr4` contains flags that control protected mode execution.
 pub mod cr4;
 
 /// A struct bundling together a snapshot of the control registers state.
 #[derive(Copy,Clone,Debug)]
 pub struct CrState { /// `$cr0` contains flags that control the CPU's operations
-                     pub cr0: usize
+                     pub cr0: cr0::Flags
                    , /// `$cr2` contains the page fault linear address
                      pub cr2: usize
                    , /// `$cr3` contains the page table root pointer
-                     pub cr3: usize
+                     pub cr3: cr3::Cr3
                    , /// `$cr4` contains flags that control operations in
                      ///  protected mode
                      pub cr4: cr4::Flags
+                   , /// `XCR0` contains the set of extended processor
+                     ///  states (x87, SSE, AVX, ...) enabled for use by
+                     ///  `xsave`/`xrstor`. Zero if `CR4.OSXSAVE` is unset,
+                     ///  since reading it would otherwise `#UD`.
+                     pub xcr0: u64
                    }
 
 impl fmt::Display for CrState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-         write!( f, "CR0: {:#08x} CR2: {:#08x} CR3: {:#08x} CR4: {:#08x}"
-                , self.cr0, self.cr2, self.cr3, self.cr4)
+         write!( f, "CR0: {:?} CR2: {:#08x} CR3: {} CR4: {:?} XCR0: {:#08x}"
+                , self.cr0, self.cr2, self.cr3, self.cr4, self.xcr0)
     }
 }
 
 /// Dump the current contents of the control registers to a `CrState`.
 pub fn dump() -> CrState {
-    let cr0_: usize; let cr2_: usize;
+    let cr0_: cr0::Flags; let cr2_: usize;
     let cr3_: usize; let cr4_: cr4::Flags;
     unsafe {
         asm!(  "mov $0, cr0
@@ -48,25 +57,27 @@ pub fn dump() -> CrState {
             ::: "intel"
               , "volatile");
     }
-    CrState { cr0: cr0_, cr2: cr2_, cr3: cr3_, cr4: cr4_ }
+    // `xgetbv` is only valid once the OS has opted in via `CR4.OSXSAVE`;
+    // reading it otherwise would raise `#UD`.
+    let xcr0_ = if cr4_.contains(cr4::OSXSAVE) { unsafe { xcr0_read() } } else { 0 };
+    CrState { cr0: cr0_, cr2: cr2_, cr3: cr3::Cr3::from(cr3_), cr4: cr4_, xcr0: xcr0_ }
 
 }
 
 /// Set the write protect bit in `cr0`.
 pub fn set_write_protect() {
-    let wp_bit = 1 << 16;
-    unsafe { cr0_write(cr0_read() | wp_bit) };
+    unsafe { cr0_write(cr0_read() | cr0::WP) };
 }
 
 /// Read the current value from `$cr0`.
-pub fn cr0_read() -> usize {
+pub fn cr0_read() -> cr0::Flags {
     let result: usize;
     unsafe {
         asm!(   "mov $0, cr0"
             :   "=r"(result)
             ::: "intel" );
     }
-    result
+    cr0::Flags::from_bits_truncate(result)
 }
 
 /// Write a value to `$cr0`.
@@ -74,9 +85,9 @@ pub fn cr0_read() -> usize {
 /// # Unsafe Because:
 ///  - Control registers should generally not be modified during normal
 ///    operation.
-pub unsafe fn cr0_write(value: usize) {
+pub unsafe fn cr0_write(value: cr0::Flags) {
     asm!(  "mov cr0, $0"
-        :: "r"(value)
+        :: "r"(value.bits())
         :: "intel");
 }
 
@@ -123,3 +134,109 @@ pub unsafe fn cr3_write(value: usize) {
         :: "r"(value)
         :: "intel");
 }
+
+/// Read the current value from `$cr4`.
+pub fn cr4_read() -> cr4::Flags {
+    let result: usize;
+    unsafe {
+        asm!(   "mov $0, cr4"
+            :   "=r"(result)
+            ::: "intel" );
+    }
+    cr4::Flags::from_bits_truncate(result)
+}
+
+/// Write a value to `$cr4`.
+///
+/// # Unsafe Because:
+///  - Control registers should generally not be modified during normal
+///    operation.
+pub unsafe fn cr4_write(value: cr4::Flags) {
+    asm!(  "mov cr4, $0"
+        :: "r"(value.bits())
+        :: "intel");
+}
+
+/// Read the current value of `XCR0`, the extended-state-enable register,
+/// via `xgetbv`.
+///
+/// # Unsafe Because:
+///  - `xgetbv` raises `#UD` unless `CR4.OSXSAVE` is set.
+pub unsafe fn xcr0_read() -> u64 {
+    let lo: u32; let hi: u32;
+    asm!(  "xgetbv"
+        :   "={eax}"(lo), "={edx}"(hi)
+        :   "{ecx}"(0u32)
+        ::  "intel" );
+    ((hi as u64) << 32) | lo as u64
+}
+
+/// Write a value to `XCR0`, the extended-state-enable register, via
+/// `xsetbv`.
+///
+/// # Unsafe Because:
+///  - `xsetbv` raises `#UD` unless `CR4.OSXSAVE` is set, and `#GP` if
+///    `value` enables a state component the CPU does not support.
+pub unsafe fn xcr0_write(value: u64) {
+    let lo = value as u32;
+    let hi = (value >> 32) as u32;
+    asm!(  "xsetbv"
+        :: "{eax}"(lo), "{edx}"(hi), "{ecx}"(0u32)
+        :: "intel" );
+}
+
+/// `XCR0` bit enabling x87 FPU state.
+const XCR0_X87: u64 = 1 << 0;
+/// `XCR0` bit enabling SSE state.
+const XCR0_SSE: u64 = 1 << 1;
+/// `XCR0` bit enabling AVX (YMM) state.
+const XCR0_AVX: u64 = 1 << 2;
+
+/// `CPUID.(EAX=1):ECX` bit reporting XSAVE/XGETBV/XSETBV support.
+const CPUID_ECX_XSAVE: u32 = 1 << 26;
+/// `CPUID.(EAX=1):ECX` bit reporting AVX support.
+const CPUID_ECX_AVX: u32 = 1 << 28;
+
+/// Query `CPUID` leaf 1 and return its `ecx` output.
+fn cpuid_1_ecx() -> u32 {
+    let ecx: u32;
+    unsafe {
+        asm!(  "mov eax, 1
+                cpuid"
+            :   "={ecx}"(ecx)
+            ::  "eax", "ebx", "edx"
+            :   "intel" );
+    }
+    ecx
+}
+
+/// Enable use of SSE and (if available) AVX extended processor state.
+///
+/// This sets `CR4.OSFXSR`/`CR4.OSXMMEXCPT` so `fxsave`/`fxrstor` and
+/// unmasked SSE exceptions are usable. If `CPUID` reports XSAVE support,
+/// it then sets `CR4.OSXSAVE` so `xsetbv`/`xgetbv` are usable and enables
+/// the x87 and SSE bits in `XCR0`; without XSAVE support, `CR4.OSXSAVE`
+/// and `XCR0` are left untouched. The AVX bit is only set when `CPUID`
+/// also reports AVX support, since setting an unsupported `XCR0` bit
+/// raises `#GP`.
+///
+/// # Unsafe Because:
+///  - Modifies `$cr4` and `XCR0`, which affect how the CPU handles
+///    floating-point and SIMD state for every task.
+pub unsafe fn enable_extended_state() {
+    cr4_write(cr4_read() | cr4::OSFXSR | cr4::OSXMMEXCPT);
+
+    let ecx = cpuid_1_ecx();
+    if ecx & CPUID_ECX_XSAVE == 0 {
+        // No XSAVE support; there is nothing more we can safely enable.
+        return;
+    }
+
+    cr4_write(cr4_read() | cr4::OSXSAVE);
+
+    let mut xcr0 = xcr0_read() | XCR0_X87 | XCR0_SSE;
+    if ecx & CPUID_ECX_AVX != 0 {
+        xcr0 |= XCR0_AVX;
+    }
+    xcr0_write(xcr0);
+}
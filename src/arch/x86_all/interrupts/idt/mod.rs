@@ -11,6 +11,7 @@
 use core::{fmt, mem, convert, ptr};
 use core::fmt::Write;
 
+use elf;
 use memory::PAddr;
 
 use arch::cpu::{dtable, control_regs};
@@ -34,6 +35,181 @@ pub type Handler = unsafe extern "C" fn() -> !;
 /// Number of entries in the system's Interrupt Descriptor Table.
 pub const ENTRIES: usize = 256;
 
+//==------------------------------------------------------------------------==
+//  Page faults
+bitflags! {
+    /// Flags decoded from the error code pushed onto the stack for a
+    /// page-fault (vector 14) exception.
+    ///
+    /// See the _Intel® 64 and IA-32 Architectures Software Developer's
+    /// Manual_, Volume 3A, Section 4.7, "Page-Fault Exceptions."
+    pub flags PageFaultError: usize {
+        /// If set, the fault was caused by a page-protection violation;
+        /// if unset, the fault was caused by a non-present page.
+        const PF_PROTECTION = 1 << 0
+      , /// If set, the fault was caused by a write access; if unset, by a
+        /// read access.
+        const PF_WRITE = 1 << 1
+      , /// If set, the fault occurred while the CPU was executing in user
+        /// mode; if unset, the CPU was in supervisor mode.
+        const PF_USER = 1 << 2
+      , /// If set, the fault was caused by a reserved bit set to 1 in some
+        /// paging-structure entry.
+        const PF_RESERVED = 1 << 3
+      , /// If set, the fault was caused by an instruction fetch.
+        const PF_INSTR_FETCH = 1 << 4
+      , /// If set, the fault was caused by a protection-key violation.
+        const PF_PROTECTION_KEY = 1 << 5
+      , /// If set, the fault was caused by SGX-specific access-control
+        /// requirements (not related to ordinary paging).
+        const PF_SGX = 1 << 15
+    }
+}
+
+/// A callback invoked to attempt recovery from a recoverable page fault.
+///
+/// Takes the faulting linear address (read from `$cr2`) and the decoded
+/// `PageFaultError`, and returns `true` if the fault was handled (e.g. by
+/// mapping in a demand-paged page), or `false` if it was not and the fault
+/// should be treated as fatal.
+pub type PageFaultHandler = fn(vaddr: usize, cause: PageFaultError) -> bool;
+
+/// The currently registered page-fault handler, if any.
+static mut PAGE_FAULT_HANDLER: Option<PageFaultHandler> = None;
+
+/// Register a callback to handle recoverable page faults.
+///
+/// This is the hook demand-paging (and similar lazily-populated mappings)
+/// is expected to use: when a fault is caused by a non-present page rather
+/// than a protection violation, the registered handler is given the
+/// faulting address and may map in a frame to satisfy it.
+pub unsafe fn set_page_fault_handler(handler: PageFaultHandler) {
+    PAGE_FAULT_HANDLER = Some(handler);
+}
+
+//==------------------------------------------------------------------------==
+//  Backtraces
+/// The kernel's own parsed ELF image, used to resolve return addresses to
+/// symbol names when printing a backtrace.
+static mut KERNEL_IMAGE: Option<&'static elf::Image<'static>> = None;
+
+/// Register the kernel's ELF image so that exception backtraces can
+/// resolve addresses to symbol names.
+///
+/// This must be called once, early during boot, before any exception that
+/// wants a symbolicated backtrace can occur.
+pub unsafe fn set_kernel_image(image: &'static elf::Image<'static>) {
+    KERNEL_IMAGE = Some(image);
+}
+
+/// Walk the frame-pointer chain starting at `rbp`, printing each return
+/// address resolved through the registered [kernel image](fn.set_kernel_image.html).
+///
+/// This relies on the kernel having been compiled with frame pointers
+/// enabled: each stack frame is expected to begin with the caller's saved
+/// `rbp`, followed immediately by the return address.
+pub unsafe fn backtrace(mut rbp: usize) {
+    let _ = write!( CONSOLE.lock().set_colors(Color::White, Color::Blue)
+                  , "Backtrace:\n" );
+
+    while rbp != 0 && rbp % mem::align_of::<usize>() == 0 {
+        let saved_rbp = *(rbp as *const usize);
+        let return_addr = *((rbp + mem::size_of::<usize>()) as *const usize);
+        if return_addr == 0 {
+            break;
+        }
+
+        match KERNEL_IMAGE.and_then(|image| image.resolve(return_addr)) {
+            Some((name, offset)) =>
+                { let _ = write!( CONSOLE.lock().set_colors(Color::White, Color::Blue)
+                                , "  {:#018x}  {} + {:#x}\n"
+                                , return_addr, name, offset ); }
+          , None =>
+                { let _ = write!( CONSOLE.lock().set_colors(Color::White, Color::Blue)
+                                , "  {:#018x}  <unknown>\n"
+                                , return_addr ); }
+        }
+
+        if saved_rbp == 0 || saved_rbp <= rbp {
+            break;
+        }
+        rbp = saved_rbp;
+    }
+}
+
+//==------------------------------------------------------------------------==
+//  Runtime interrupt handler registration
+//
+//  The assembly IRQ trampolines exported as `interrupt_handlers` push
+//  their own vector number and fall through to `dispatch_interrupt`
+//  below. CPU-exception vectors (0-31) are routed from there straight
+//  into the existing `Idt::handle_cpu_exception` path, so the page-fault
+//  decoding and backtrace printing wired up there keep running
+//  unconditionally. Only IRQ vectors (`>= IRQ_BASE`) are looked up in the
+//  registry below, which is what lets a driver attach its own Rust
+//  service routine to a vector at runtime, without editing the static
+//  assembly table.
+
+/// A runtime-registered interrupt service routine.
+///
+/// Unlike [`Handler`](type.Handler.html), which is the raw, assembly-ABI
+/// entry point installed directly into a `Gate`, a `VectorHandler` is an
+/// ordinary Rust function called by [`dispatch_interrupt`](fn.dispatch_interrupt.html)
+/// with interrupts masked (the gate's `GateType::Interrupt` clears `IF` on
+/// entry) and given the saved `InterruptContext`.
+pub type VectorHandler = fn(&InterruptContext);
+
+/// The runtime-registered handlers, indexed by vector number.
+static mut HANDLERS: [Option<VectorHandler>; ENTRIES] = [None; ENTRIES];
+
+/// The first vector number used for remapped hardware IRQs, once the
+/// legacy PIC has been reprogrammed out of the CPU exception range.
+const IRQ_BASE: u8 = 32;
+
+/// `out al, dx` -- write a byte to an I/O port.
+unsafe fn outb(port: u16, value: u8) {
+    asm!(  "out dx, al"
+        :: "{dx}"(port), "{al}"(value)
+        :: "intel" );
+}
+
+/// Send an end-of-interrupt to the (possibly cascaded) legacy 8259
+/// Programmable Interrupt Controller for the given IRQ vector.
+unsafe fn send_eoi(vector: u8) {
+    const PIC1_CMD: u16 = 0x20;
+    const PIC2_CMD: u16 = 0xA0;
+    const EOI: u8 = 0x20;
+
+    if vector - IRQ_BASE >= 8 {
+        outb(PIC2_CMD, EOI);
+    }
+    outb(PIC1_CMD, EOI);
+}
+
+/// The single Rust-side dispatcher every assembly trampoline in
+/// `interrupt_handlers` falls through to.
+///
+/// CPU-exception vectors (`< IRQ_BASE`) are routed directly into
+/// `Idt::handle_cpu_exception`, exactly as they were before this
+/// registry existed -- nothing registers a `HANDLERS` entry for them, so
+/// they must never be looked up there instead, or their diagnostics
+/// would silently stop running. Only IRQ vectors consult `HANDLERS`,
+/// and get an end-of-interrupt sent to the PIC once their handler
+/// returns, so drivers never need to remember to do so themselves.
+#[no_mangle]
+pub unsafe extern "C" fn dispatch_interrupt(vector: usize, state: *const InterruptContext) {
+    if vector < IRQ_BASE as usize {
+        Idt::handle_cpu_exception(&*state);
+        return;
+    }
+
+    if let Some(handler) = HANDLERS[vector] {
+        handler(&*state);
+    }
+
+    send_eoi(vector as u8);
+}
+
 //==------------------------------------------------------------------------==
 //  IDT Gates
 #[cfg(target_arch = "x86")]    #[path = "gate32.rs"] pub mod gate;
@@ -94,8 +270,48 @@ impl Idt {
         self.0[idx] = Gate::from(handler)
     }
 
+    /// Register a Rust function to handle the given interrupt vector.
+    ///
+    /// This takes neither `&self` nor `&mut self`: it mutates the one
+    /// process-global `HANDLERS` table that `dispatch_interrupt` reads,
+    /// not anything owned by a particular `Idt` instance, so it is
+    /// exposed as an associated function rather than a method. It also
+    /// does not touch the gate itself -- the vector's gate must already
+    /// point at one of the `interrupt_handlers` trampolines (see
+    /// `add_handlers`), which falls through to `dispatch_interrupt` and
+    /// looks `handler` back up from here.
+    ///
+    /// `handler` may be a non-capturing closure as well as a plain
+    /// function -- both coerce to the bare `fn` pointer `VectorHandler`
+    /// requires. A closure that captures state does not coerce to `fn`
+    /// and cannot be registered this way.
+    ///
+    /// # Unsafe Because:
+    ///  - Writes the process-global `HANDLERS` table with no
+    ///    synchronization, the same as `set_page_fault_handler` and
+    ///    `set_kernel_image` above. A call racing `dispatch_interrupt` on
+    ///    the same core (i.e. made with interrupts enabled) can observe a
+    ///    torn write.
+    pub unsafe fn register(vector: usize, handler: VectorHandler) {
+        HANDLERS[vector] = Some(handler);
+    }
+
+    /// Remove any handler registered for the given interrupt vector.
+    ///
+    /// # Unsafe Because:
+    ///  - See `register`, above.
+    pub unsafe fn unregister(vector: usize) {
+        HANDLERS[vector] = None;
+    }
+
     /// Handle a CPU exception with a given interrupt context.
-    pub unsafe fn handle_cpu_exception(state: &InterruptContext) -> ! {
+    ///
+    /// Returns normally if the fault was claimed by a registered
+    /// recoverable handler (see `set_page_fault_handler`) -- the caller
+    /// is expected to `iret` back into the faulting context in that case.
+    /// For every other exception, including an unclaimed page fault,
+    /// this prints a fatal diagnostic dump and never returns.
+    pub unsafe fn handle_cpu_exception(state: &InterruptContext) {
         let ex_info = state.exception();
         let cr_state = control_regs::dump();
         let _ = write!( CONSOLE.lock()
@@ -108,17 +324,47 @@ impl Idt {
                       , ex_info.irq_type, state.int_id, state.err_no
                       , ex_info.source );
 
-        // TODO: parse error codes
-        let _ = match state.int_id {
-            14 => unimplemented!() //TODO: special handling for page faults
-           , _ => write!( CONSOLE.lock()
-                                 .set_colors(Color::White, Color::Blue)
-                        , "Registers:\n{:?}\n    {}\n"
-                        , state.registers
-                        , cr_state
-                        )
+        let claimed = match state.int_id {
+            14 => {
+                let error = PageFaultError::from_bits_truncate(state.err_no as usize);
+                // A non-present page (rather than a protection violation)
+                // may be recoverable, e.g. by a demand-paging handler.
+                let recoverable = !error.contains(PF_PROTECTION);
+                let handled = recoverable && PAGE_FAULT_HANDLER
+                    .map_or(false, |handler| handler(cr_state.cr2, error));
+
+                if !handled {
+                    let _ = write!( CONSOLE.lock()
+                                  .set_colors(Color::White, Color::Blue)
+                                , "Page fault {} address {:#x}: {:?}\n\
+                                   Registers:\n{:?}\n    {}\n"
+                                , if error.contains(PF_WRITE) { "writing to" }
+                                  else { "reading from" }
+                                , cr_state.cr2
+                                , error
+                                , state.registers
+                                , cr_state
+                                );
+                }
+                handled
+            }
+          , _ => {
+                let _ = write!( CONSOLE.lock()
+                              .set_colors(Color::White, Color::Blue)
+                            , "Registers:\n{:?}\n    {}\n"
+                            , state.registers
+                            , cr_state
+                            );
+                false
+            }
         };
 
+        if claimed {
+            return;
+        }
+
+        backtrace(state.registers.rbp);
+
         loop { }
     }
 